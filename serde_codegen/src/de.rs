@@ -12,6 +12,9 @@ pub fn expand_derive_deserialize(item: &syn::MacroInput) -> Result<Tokens, Strin
         let ctxt = internals::Ctxt::new();
         let item = Item::from_ast(&ctxt, item);
         check_no_str(&ctxt, &item);
+        check_flatten_with_deny_unknown_fields(&ctxt, &item);
+        check_tag_only_on_enum(&ctxt, &item);
+        check_remote_on_supported_body(&ctxt, &item);
         try!(ctxt.check());
         item
     };
@@ -27,21 +30,97 @@ pub fn expand_derive_deserialize(item: &syn::MacroInput) -> Result<Tokens, Strin
                                 ty.clone());
 
     let where_clause = &impl_generics.where_clause;
+    let borrowed = borrowed_lifetimes(item.body.all_fields());
+    let de_impl_generics = de_generics(&impl_generics, &borrowed);
 
     let dummy_const = aster::id(format!("_IMPL_DESERIALIZE_FOR_{}", item.ident));
 
-    Ok(quote! {
-        #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
-        const #dummy_const: () = {
-            extern crate serde as _serde;
-            #[automatically_derived]
-            impl #impl_generics _serde::Deserialize for #ty #where_clause {
-                fn deserialize<__D>(deserializer: &mut __D) -> ::std::result::Result<#ty, __D::Error>
-                    where __D: _serde::Deserializer
+    match item.attrs.remote() {
+        Some(remote) => {
+            // A `#[serde(remote = "...")]` container mirrors a type we
+            // don't own, so we can't implement `Deserialize` for it (that
+            // would be an orphan impl for `remote`, or the wrong `Value`
+            // for `item.ident`). Instead emit an inherent `deserialize`
+            // function on the local mirror type that builds and returns
+            // the *remote* type, meant to be pointed at from a field of
+            // the remote type via `#[serde(with = "...")]`.
+            Ok(quote! {
+                #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
+                const #dummy_const: () = {
+                    extern crate serde as _serde;
+                    #[automatically_derived]
+                    impl #de_impl_generics #ty #where_clause {
+                        pub fn deserialize<__D>(deserializer: &mut __D) -> ::std::result::Result<#remote, __D::Error>
+                            where __D: _serde::Deserializer<'de>
+                        #body
+                    }
+                };
+            })
+        }
+        None => {
+            let in_place_body = deserialize_in_place_body(&item, &impl_generics, ty.clone());
+
+            let in_place_impl = in_place_body.map(|body| quote! {
+                #[inline]
+                fn deserialize_in_place<__D>(deserializer: &mut __D, __place: &mut Self) -> ::std::result::Result<(), __D::Error>
+                    where __D: _serde::Deserializer<'de>
                 #body
+            });
+
+            Ok(quote! {
+                #[allow(non_upper_case_globals, unused_attributes, unused_qualifications)]
+                const #dummy_const: () = {
+                    extern crate serde as _serde;
+                    #[automatically_derived]
+                    impl #de_impl_generics _serde::Deserialize<'de> for #ty #where_clause {
+                        fn deserialize<__D>(deserializer: &mut __D) -> ::std::result::Result<#ty, __D::Error>
+                            where __D: _serde::Deserializer<'de>
+                        #body
+
+                        #in_place_impl
+                    }
+                };
+            })
+        }
+    }
+}
+
+// Determines whether this type can generate a `deserialize_in_place` fast
+// path that writes directly into caller-provided storage instead of
+// building a fresh value and moving it in. Only plain named-field structs
+// qualify for now; anything else falls back to the default provided
+// method on `Deserialize`, which just calls `deserialize` and overwrites
+// `*place` with the result.
+fn deserialize_in_place_body(
+    item: &Item,
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+) -> Option<Tokens> {
+    match item.body {
+        Body::Struct(Style::Struct, ref fields) => {
+            if fields.iter().any(|field| field.ident.is_none()) {
+                return None;
             }
-        };
-    })
+
+            // `flatten` fields are replayed from a buffered `Content`, not
+            // visited directly off the input, and `deserialize_with` fields
+            // produce a value through an arbitrary function rather than
+            // writing in place — neither fits the in-place fast path, so
+            // fall back to the normal builder for those structs.
+            if fields.iter().any(|field| {
+                field.attrs.flatten() || field.attrs.deserialize_with().is_some()
+            }) {
+                return None;
+            }
+
+            Some(deserialize_struct_in_place(
+                impl_generics,
+                ty,
+                fields,
+                &item.attrs))
+        }
+        _ => None,
+    }
 }
 
 // All the generics in the input, plus a bound `T: Deserialize` for each generic
@@ -85,6 +164,48 @@ fn requires_default(attrs: &attr::Field) -> bool {
     attrs.default() == &attr::FieldDefault::Default
 }
 
+// Prepends the deserializer lifetime `'de` to `generics`, for use at any
+// `impl ... _serde::Deserialize<'de>` or `impl ... _serde::de::Visitor<'de>`
+// generated in this module. A `struct`/`impl` defined inside a function
+// body is a separate item and cannot see the enclosing function's
+// generics, so each such impl introduces its own fresh `'de` rather than
+// sharing one real Rust binder; reusing the literal name `'de` everywhere
+// just keeps the generated code readable.
+//
+// `borrowed` is every lifetime a `#[serde(borrow)]` field actually borrows
+// for (see `borrowed_lifetimes` below). `'de` is declared with those as
+// bounds, i.e. `'de: 'a`, so a field typed `&'a str` is able to satisfy
+// `&'a str: Deserialize<'de>`, which itself requires `'de: 'a`.
+fn de_generics(generics: &syn::Generics, borrowed: &[syn::Lifetime]) -> syn::Generics {
+    let mut de_lifetime = syn::LifetimeDef::new("'de");
+    de_lifetime.bounds = borrowed.to_vec();
+
+    let mut generics = generics.clone();
+    generics.lifetimes.insert(0, de_lifetime);
+    generics
+}
+
+// Collects, in first-seen order, every lifetime borrowed by a field that
+// is actually deserialized by us (fields we skip don't borrow anything
+// from the input). Used to build the `'de: 'a` bound `de_generics` needs
+// to make `&'a str`/`&'a [u8]` fields satisfy `Deserialize<'de>`.
+fn borrowed_lifetimes<'a, I>(fields: I) -> Vec<syn::Lifetime>
+    where I: IntoIterator<Item = &'a Field>
+{
+    let mut lifetimes = Vec::new();
+    for field in fields {
+        if field.attrs.skip_deserializing() {
+            continue;
+        }
+        for lifetime in field.attrs.borrowed_lifetimes() {
+            if !lifetimes.contains(lifetime) {
+                lifetimes.push(lifetime.clone());
+            }
+        }
+    }
+    lifetimes
+}
+
 fn deserialize_body(
     item: &Item,
     impl_generics: &syn::Generics,
@@ -110,7 +231,9 @@ fn deserialize_body(
                 impl_generics,
                 ty,
                 fields,
-                &item.attrs)
+                &item.attrs,
+                item.attrs.remote(),
+                false)
         }
         Body::Struct(Style::Tuple, ref fields) |
         Body::Struct(Style::Newtype, ref fields) => {
@@ -124,7 +247,9 @@ fn deserialize_body(
                 impl_generics,
                 ty,
                 fields,
-                &item.attrs)
+                &item.attrs,
+                item.attrs.remote(),
+                false)
         }
         Body::Struct(Style::Unit, _) => {
             deserialize_unit_struct(
@@ -209,7 +334,7 @@ fn deserialize_unit_struct(
     quote!({
         struct __Visitor;
 
-        impl _serde::de::Visitor for __Visitor {
+        impl<'de> _serde::de::Visitor<'de> for __Visitor {
             type Value = #type_ident;
 
             #[inline]
@@ -239,15 +364,41 @@ fn deserialize_tuple(
     ty: syn::Ty,
     fields: &[Field],
     item_attrs: &attr::Item,
+    remote: Option<&syn::Path>,
+    external_variant: bool,
 ) -> Tokens {
     let where_clause = &impl_generics.where_clause;
+    let borrowed = borrowed_lifetimes(fields);
+    let de_impl_generics = de_generics(impl_generics, &borrowed);
 
     let (visitor_item, visitor_ty, visitor_expr) = deserialize_visitor(impl_generics);
 
-    let is_enum = variant_ident.is_some();
+    // `external_variant` is only set for the externally tagged enum
+    // representation, where a `visitor: VariantVisitor` is already bound
+    // in scope and `visit_tuple` dispatches through it. Every other
+    // tuple/struct variant caller (internally/adjacently tagged, untagged)
+    // drives a `Deserializer` it bound itself, so it dispatches the same
+    // way a top-level tuple struct does.
+    let is_enum = external_variant;
     let type_path = match variant_ident {
         Some(variant_ident) => quote!(#type_ident::#variant_ident),
-        None => quote!(#type_ident),
+        None => match remote {
+            // A `#[serde(remote = "...")]` container builds the local
+            // visitor and field machinery as usual, but the final value
+            // constructed at the end is the *remote* type, not the local
+            // mirror `type_ident`.
+            Some(remote) => quote!(#remote),
+            None => quote!(#type_ident),
+        },
+    };
+
+    // The visitor built below has to produce (and the surrounding
+    // `deserialize`/`deserialize_in_place` has to return) the *remote*
+    // type when this is a `#[serde(remote = "...")]` container, not the
+    // local mirror `ty` it was handed.
+    let ty = match remote {
+        Some(remote) => syn::Ty::Path(None, remote.clone()),
+        None => ty,
     };
 
     let nfields = fields.len();
@@ -284,7 +435,7 @@ fn deserialize_tuple(
     quote!({
         #visitor_item
 
-        impl #impl_generics _serde::de::Visitor for #visitor_ty #where_clause {
+        impl #de_impl_generics _serde::de::Visitor<'de> for #visitor_ty #where_clause {
             type Value = #ty;
 
             #visit_newtype_struct
@@ -386,7 +537,7 @@ fn deserialize_newtype_struct(
         None => {
             let field_ty = &field.ty;
             quote! {
-                try!(<#field_ty as _serde::Deserialize>::deserialize(__e))
+                try!(<#field_ty as _serde::Deserialize<'de>>::deserialize(__e))
             }
         }
         Some(path) => {
@@ -395,14 +546,14 @@ fn deserialize_newtype_struct(
             quote!({
                 #wrapper
                 #wrapper_impl
-                try!(<#wrapper_ty as _serde::Deserialize>::deserialize(__e)).value
+                try!(<#wrapper_ty as _serde::Deserialize<'de>>::deserialize(__e)).value
             })
         }
     };
     quote! {
         #[inline]
         fn visit_newtype_struct<__E>(&mut self, __e: &mut __E) -> ::std::result::Result<Self::Value, __E::Error>
-            where __E: _serde::Deserializer,
+            where __E: _serde::Deserializer<'de>,
         {
             Ok(#type_path(#value))
         }
@@ -416,14 +567,27 @@ fn deserialize_struct(
     ty: syn::Ty,
     fields: &[Field],
     item_attrs: &attr::Item,
+    remote: Option<&syn::Path>,
+    external_variant: bool,
 ) -> Tokens {
     let where_clause = &impl_generics.where_clause;
+    let borrowed = borrowed_lifetimes(fields);
+    let de_impl_generics = de_generics(impl_generics, &borrowed);
 
     let (visitor_item, visitor_ty, visitor_expr) = deserialize_visitor(impl_generics);
 
     let type_path = match variant_ident {
         Some(variant_ident) => quote!(#type_ident::#variant_ident),
-        None => quote!(#type_ident),
+        None => match remote {
+            Some(remote) => quote!(#remote),
+            None => quote!(#type_ident),
+        },
+    };
+
+    // See the comment on `ty` in `deserialize_tuple`.
+    let ty = match remote {
+        Some(remote) => syn::Ty::Path(None, remote.clone()),
+        None => ty,
     };
 
     let visit_seq = deserialize_seq(
@@ -442,7 +606,8 @@ fn deserialize_struct(
         item_attrs,
     );
 
-    let is_enum = variant_ident.is_some();
+    // See the comment on `external_variant` in `deserialize_tuple`.
+    let is_enum = external_variant;
     let dispatch = if is_enum {
         quote! {
             visitor.visit_struct(FIELDS, #visitor_expr)
@@ -459,7 +624,7 @@ fn deserialize_struct(
 
         #visitor_item
 
-        impl #impl_generics _serde::de::Visitor for #visitor_ty #where_clause {
+        impl #de_impl_generics _serde::de::Visitor<'de> for #visitor_ty #where_clause {
             type Value = #ty;
 
             #[inline]
@@ -483,27 +648,255 @@ fn deserialize_struct(
     })
 }
 
+// The `deserialize_in_place` counterpart to `deserialize_struct`. Rather
+// than accumulating `Option<T>` locals and building `#type_path { .. }` at
+// the end, each field is deserialized straight into `&mut place.field`,
+// which lets a pre-existing `Vec`/`String`/`HashMap` in that field be
+// reused instead of reallocated. Fields absent from the input are reset
+// to their `expr_is_missing` default so the result matches a from-scratch
+// deserialize.
+fn deserialize_struct_in_place(
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+    fields: &[Field],
+    item_attrs: &attr::Item,
+) -> Tokens {
+    let where_clause = &impl_generics.where_clause;
+    let type_name = item_attrs.name().deserialize_name();
+
+    let mut struct_generics = impl_generics.clone();
+    struct_generics.lifetimes.insert(0, syn::LifetimeDef::new("'__a"));
+    let borrowed = borrowed_lifetimes(fields);
+    let de_struct_generics = de_generics(&struct_generics, &borrowed);
+
+    let field_exprs = fields.iter()
+        .map(|field| field_names_with_aliases(
+            field.attrs.name().deserialize_name(),
+            field.attrs.aliases()))
+        .collect();
+    let field_visitor = deserialize_field_visitor(field_exprs, item_attrs, false);
+
+    let field_names = fields.iter().map(|field| {
+        field.ident.clone().expect("struct contains unnamed field").to_string()
+    });
+    let fields_stmt = quote! {
+        const FIELDS: &'static [&'static str] = &[ #(#field_names),* ];
+    };
+
+    let seen_flags: Vec<_> = (0 .. fields.len())
+        .map(|i| aster::id(format!("__field{}_seen", i)))
+        .collect();
+
+    let let_seen: Vec<_> = seen_flags.iter()
+        .map(|flag| quote!(let mut #flag: bool = false;))
+        .collect();
+
+    let value_arms: Vec<_> = fields.iter()
+        .enumerate()
+        .filter(|&(_, field)| !field.attrs.skip_deserializing())
+        .map(|(i, field)| {
+            let field_ident = field.ident.clone().expect("struct contains unnamed field");
+            let name = aster::id(format!("__field{}", i));
+            let seen = &seen_flags[i];
+            let deser_name = field.attrs.name().deserialize_name();
+            quote! {
+                __Field::#name => {
+                    if #seen {
+                        return Err(<__V::Error as _serde::de::Error>::duplicate_field(#deser_name));
+                    }
+                    try!(visitor.visit_value_in_place(&mut self.place.#field_ident));
+                    #seen = true;
+                }
+            }
+        })
+        .collect();
+
+    let skipped_arms: Vec<_> = fields.iter()
+        .enumerate()
+        .filter(|&(_, field)| field.attrs.skip_deserializing())
+        .map(|(i, _)| {
+            let name = aster::id(format!("__field{}", i));
+            quote! {
+                __Field::#name => {
+                    let _ = try!(visitor.visit_value::<_serde::de::impls::IgnoredAny>());
+                }
+            }
+        })
+        .collect();
+
+    let ignored_arm = if item_attrs.deny_unknown_fields() {
+        None
+    } else {
+        Some(quote! {
+            __Field::__ignore => { let _ = try!(visitor.visit_value::<_serde::de::impls::IgnoredAny>()); }
+        })
+    };
+
+    let reset_missing: Vec<_> = fields.iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let field_ident = field.ident.clone().expect("struct contains unnamed field");
+            let seen = &seen_flags[i];
+            let missing_expr = expr_is_missing(&field.attrs);
+            if field.attrs.skip_deserializing() {
+                return quote! {
+                    self.place.#field_ident = #missing_expr;
+                };
+            }
+            quote! {
+                if !#seen {
+                    self.place.#field_ident = #missing_expr;
+                }
+            }
+        })
+        .collect();
+
+    // Mirrors `deserialize_seq`, but writes each field straight into
+    // `self.place.#field_ident` via `visit_in_place` instead of binding
+    // `__fieldN` locals and building a fresh struct literal at the end.
+    let mut index_in_seq = 0usize;
+    let visit_seq_stmts: Vec<_> = fields.iter()
+        .map(|field| {
+            let field_ident = field.ident.clone().expect("struct contains unnamed field");
+            if field.attrs.skip_deserializing() {
+                let default = expr_is_missing(&field.attrs);
+                quote! {
+                    self.place.#field_ident = #default;
+                }
+            } else {
+                let stmt = quote! {
+                    if !try!(visitor.visit_in_place(&mut self.place.#field_ident)) {
+                        try!(visitor.end());
+                        return Err(_serde::de::Error::invalid_length(#index_in_seq));
+                    }
+                };
+                index_in_seq += 1;
+                stmt
+            }
+        })
+        .collect();
+
+    quote!({
+        #field_visitor
+
+        struct __Visitor #struct_generics #where_clause {
+            place: &'__a mut #ty,
+        }
+
+        impl #de_struct_generics _serde::de::Visitor<'de> for __Visitor #struct_generics #where_clause {
+            type Value = ();
+
+            #[inline]
+            fn visit_seq<__V>(&mut self, mut visitor: __V) -> ::std::result::Result<(), __V::Error>
+                where __V: _serde::de::SeqVisitor
+            {
+                #(#visit_seq_stmts)*
+
+                try!(visitor.end());
+
+                Ok(())
+            }
+
+            #[inline]
+            fn visit_map<__V>(&mut self, mut visitor: __V) -> ::std::result::Result<(), __V::Error>
+                where __V: _serde::de::MapVisitor
+            {
+                #(#let_seen)*
+
+                while let Some(__key) = try!(visitor.visit_key::<__Field>()) {
+                    match __key {
+                        #(#value_arms)*
+                        #(#skipped_arms)*
+                        #ignored_arm
+                    }
+                }
+
+                try!(visitor.end());
+
+                #(#reset_missing)*
+
+                Ok(())
+            }
+        }
+
+        #fields_stmt
+
+        deserializer.deserialize_struct(#type_name, FIELDS, __Visitor { place: __place })
+    })
+}
+
 fn deserialize_item_enum(
     type_ident: &syn::Ident,
     impl_generics: &syn::Generics,
     ty: syn::Ty,
     variants: &[Variant],
     item_attrs: &attr::Item
+) -> Tokens {
+    match *item_attrs.tag() {
+        attr::EnumTag::External => {
+            deserialize_externally_tagged_enum(
+                type_ident,
+                impl_generics,
+                ty,
+                variants,
+                item_attrs)
+        }
+        attr::EnumTag::Internal { ref tag } => {
+            deserialize_internally_tagged_enum(
+                type_ident,
+                impl_generics,
+                ty,
+                variants,
+                item_attrs,
+                tag)
+        }
+        attr::EnumTag::Adjacent { ref tag, ref content } => {
+            deserialize_adjacently_tagged_enum(
+                type_ident,
+                impl_generics,
+                ty,
+                variants,
+                item_attrs,
+                tag,
+                content)
+        }
+        attr::EnumTag::None => {
+            deserialize_untagged_enum(
+                type_ident,
+                impl_generics,
+                ty,
+                variants,
+                item_attrs)
+        }
+    }
+}
+
+fn deserialize_externally_tagged_enum(
+    type_ident: &syn::Ident,
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+    variants: &[Variant],
+    item_attrs: &attr::Item
 ) -> Tokens {
     let where_clause = &impl_generics.where_clause;
+    let borrowed = borrowed_lifetimes(variants.iter().flat_map(|variant| variant.fields.iter()));
+    let de_impl_generics = de_generics(impl_generics, &borrowed);
 
     let type_name = item_attrs.name().deserialize_name();
 
     let variant_visitor = deserialize_field_visitor(
         variants.iter()
             .filter(|variant| !variant.attrs.skip_deserializing())
-            .map(|variant| variant.attrs.name().deserialize_name())
+            .map(|variant| field_names_with_aliases(
+                variant.attrs.name().deserialize_name(),
+                variant.attrs.aliases()))
             .collect(),
         item_attrs,
         true,
     );
 
-    let variant_names = variants.iter().map(|variant| variant.ident.to_string());
+    let variant_names = variants.iter()
+        .map(|variant| variant.attrs.name().deserialize_name());
 
     let variants_stmt = quote! {
         const VARIANTS: &'static [&'static str] = &[ #(#variant_names),* ];
@@ -553,7 +946,7 @@ fn deserialize_item_enum(
 
         #visitor_item
 
-        impl #impl_generics _serde::de::Visitor for #visitor_ty #where_clause {
+        impl #de_impl_generics _serde::de::Visitor<'de> for #visitor_ty #where_clause {
             type Value = #ty;
 
             fn visit_enum<__V>(&mut self, mut visitor: __V) -> ::std::result::Result<#ty, __V::Error>
@@ -601,6 +994,8 @@ fn deserialize_variant(
                 ty,
                 &variant.fields,
                 item_attrs,
+                None,
+                true,
             )
         }
         Style::Struct => {
@@ -611,11 +1006,352 @@ fn deserialize_variant(
                 ty,
                 &variant.fields,
                 item_attrs,
+                None,
+                true,
             )
         }
     }
 }
 
+// Generates the visitor for an internally tagged enum: the whole value is
+// buffered into `Content` so the tag field can be located wherever it
+// appears, then the matching variant is deserialized from what was
+// buffered (tag entry included, so struct variants can still see all of
+// their fields).
+fn deserialize_internally_tagged_enum(
+    type_ident: &syn::Ident,
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+    variants: &[Variant],
+    item_attrs: &attr::Item,
+    tag: &str,
+) -> Tokens {
+    let variant_arms: Vec<_> = variants.iter()
+        .filter(|variant| !variant.attrs.skip_deserializing())
+        .map(|variant| {
+            let variant_name = variant.attrs.name().deserialize_name();
+            let block = deserialize_content_variant(
+                type_ident,
+                impl_generics,
+                ty.clone(),
+                variant,
+                item_attrs,
+                &quote!(__D::Error),
+            );
+            quote! {
+                #variant_name => { #block }
+            }
+        })
+        .collect();
+
+    quote!({
+        let __content = try!(<_serde::private::de::Content as _serde::Deserialize<'de>>::deserialize(deserializer));
+
+        let __tag = match _serde::private::de::internally_tagged_tag(&__content, #tag) {
+            Some(__tag) => __tag,
+            None => {
+                return Err(_serde::de::Error::missing_field(#tag));
+            }
+        };
+
+        match &__tag[..] {
+            #(#variant_arms)*
+            _ => Err(_serde::de::Error::unknown_variant(&__tag)),
+        }
+    })
+}
+
+// Generates the visitor for an adjacently tagged enum, where the tag and
+// the variant's content live under two separate keys of an outer map,
+// e.g. `{"tag": "B", "content": {...}}`. Since the tag may arrive before
+// or after the content, the content is buffered into `Content` until the
+// tag is known, then replayed into the matching variant.
+fn deserialize_adjacently_tagged_enum(
+    type_ident: &syn::Ident,
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+    variants: &[Variant],
+    item_attrs: &attr::Item,
+    tag: &str,
+    content: &str,
+) -> Tokens {
+    let type_name = item_attrs.name().deserialize_name();
+    let where_clause = &impl_generics.where_clause;
+    let borrowed = borrowed_lifetimes(variants.iter().flat_map(|variant| variant.fields.iter()));
+    let de_impl_generics = de_generics(impl_generics, &borrowed);
+
+    let variant_arms: Vec<_> = variants.iter()
+        .filter(|variant| !variant.attrs.skip_deserializing())
+        .map(|variant| {
+            let variant_name = variant.attrs.name().deserialize_name();
+            let block = deserialize_content_variant(
+                type_ident,
+                impl_generics,
+                ty.clone(),
+                variant,
+                item_attrs,
+                &quote!(__V::Error),
+            );
+            quote! {
+                #variant_name => { #block }
+            }
+        })
+        .collect();
+
+    let (visitor_item, visitor_ty, visitor_expr) = deserialize_visitor(impl_generics);
+
+    quote!({
+        #[allow(non_camel_case_types)]
+        enum __Field { __field0, __field1, __ignore }
+
+        impl<'de> _serde::Deserialize<'de> for __Field {
+            #[inline]
+            fn deserialize<__D>(deserializer: &mut __D) -> ::std::result::Result<__Field, __D::Error>
+                where __D: _serde::Deserializer<'de>,
+            {
+                struct __FieldVisitor;
+
+                impl<'de> _serde::de::Visitor<'de> for __FieldVisitor {
+                    type Value = __Field;
+
+                    fn visit_str<__E>(&mut self, value: &str) -> ::std::result::Result<__Field, __E>
+                        where __E: _serde::de::Error
+                    {
+                        match value {
+                            #tag => Ok(__Field::__field0),
+                            #content => Ok(__Field::__field1),
+                            _ => Ok(__Field::__ignore),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_struct_field(__FieldVisitor)
+            }
+        }
+
+        #visitor_item
+
+        impl #de_impl_generics _serde::de::Visitor<'de> for #visitor_ty #where_clause {
+            type Value = #ty;
+
+            #[inline]
+            fn visit_map<__V>(&mut self, mut visitor: __V) -> ::std::result::Result<#ty, __V::Error>
+                where __V: _serde::de::MapVisitor
+            {
+                let mut __tag: Option<String> = None;
+                let mut __content: Option<_serde::private::de::Content> = None;
+
+                while let Some(__key) = try!(visitor.visit_key::<__Field>()) {
+                    match __key {
+                        __Field::__field0 => {
+                            if __tag.is_some() {
+                                return Err(<__V::Error as _serde::de::Error>::duplicate_field(#tag));
+                            }
+                            __tag = Some(try!(visitor.visit_value()));
+                        }
+                        __Field::__field1 => {
+                            if __content.is_some() {
+                                return Err(<__V::Error as _serde::de::Error>::duplicate_field(#content));
+                            }
+                            __content = Some(try!(visitor.visit_value()));
+                        }
+                        __Field::__ignore => {
+                            let _ = try!(visitor.visit_value::<_serde::de::impls::IgnoredAny>());
+                        }
+                    }
+                }
+
+                let __tag = match __tag {
+                    Some(__tag) => __tag,
+                    None => return Err(<__V::Error as _serde::de::Error>::missing_field(#tag)),
+                };
+                let __content = match __content {
+                    Some(__content) => __content,
+                    None => return Err(<__V::Error as _serde::de::Error>::missing_field(#content)),
+                };
+
+                try!(visitor.end());
+
+                match &__tag[..] {
+                    #(#variant_arms)*
+                    _ => Err(<__V::Error as _serde::de::Error>::unknown_variant(&__tag)),
+                }
+            }
+        }
+
+        const FIELDS: &'static [&'static str] = &[#tag, #content];
+
+        deserializer.deserialize_struct(#type_name, FIELDS, #visitor_expr)
+    })
+}
+
+// Generates the visitor for an untagged enum: the input is buffered once
+// into `Content`, then each variant is tried in declaration order against
+// a *reference* to that buffer. Trying against a reference rather than
+// consuming the buffer means a failed attempt never loses data that a
+// later variant still needs to look at.
+fn deserialize_untagged_enum(
+    type_ident: &syn::Ident,
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+    variants: &[Variant],
+    item_attrs: &attr::Item,
+) -> Tokens {
+    let attempts: Vec<_> = variants.iter()
+        .filter(|variant| !variant.attrs.skip_deserializing())
+        .map(|variant| {
+            deserialize_untagged_variant(
+                type_ident,
+                impl_generics,
+                ty.clone(),
+                variant,
+                item_attrs,
+            )
+        })
+        .collect();
+
+    quote!({
+        let __content = try!(<_serde::private::de::Content as _serde::Deserialize<'de>>::deserialize(deserializer));
+
+        #(
+            if let Ok(__ok) = #attempts {
+                return Ok(__ok);
+            }
+        )*
+
+        Err(_serde::de::Error::custom(
+            "data did not match any variant"))
+    })
+}
+
+// Attempts to deserialize a single variant from a reference to previously
+// buffered `Content`, for use by the untagged representation. Returning a
+// `Result` (rather than propagating errors with `try!`) is what lets the
+// caller silently move on to the next variant.
+fn deserialize_untagged_variant(
+    type_ident: &syn::Ident,
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+    variant: &Variant,
+    item_attrs: &attr::Item,
+) -> Tokens {
+    let variant_ident = &variant.ident;
+
+    let block = match variant.style {
+        Style::Unit => {
+            quote! {
+                _serde::Deserialize::deserialize(&mut deserializer)
+                    .map(|()| #type_ident::#variant_ident)
+            }
+        }
+        Style::Newtype => {
+            let field_ty = &variant.fields[0].ty;
+            quote! {
+                <#field_ty as _serde::Deserialize<'de>>::deserialize(&mut deserializer)
+                    .map(#type_ident::#variant_ident)
+            }
+        }
+        Style::Tuple => {
+            deserialize_tuple(
+                type_ident,
+                Some(variant_ident),
+                impl_generics,
+                ty,
+                &variant.fields,
+                item_attrs,
+                None,
+                false,
+            )
+        }
+        Style::Struct => {
+            deserialize_struct(
+                type_ident,
+                Some(variant_ident),
+                impl_generics,
+                ty,
+                &variant.fields,
+                item_attrs,
+                None,
+                false,
+            )
+        }
+    };
+
+    quote!({
+        let mut deserializer =
+            _serde::private::de::ContentRefDeserializer::<_serde::private::de::Content>::new(&__content);
+        (move || -> ::std::result::Result<#ty, _serde::private::de::ContentRefDeserializerError> {
+            #block
+        })()
+    })
+}
+
+// Deserializes a single enum variant out of previously-buffered `Content`.
+// Shared by the internally and adjacently tagged representations: both
+// know the variant before they know how to interpret its payload, so the
+// payload is replayed through a `ContentDeserializer` instead of the
+// original input deserializer. `error` is the error type the caller's
+// enclosing function or method is generic over (`__D::Error` for the
+// internally tagged case, which runs inline in `deserialize`; `__V::Error`
+// for the adjacently tagged case, which runs inside `visit_map`) — the
+// `ContentDeserializer` has to be parameterized with whichever one is
+// actually in scope at the call site.
+fn deserialize_content_variant(
+    type_ident: &syn::Ident,
+    impl_generics: &syn::Generics,
+    ty: syn::Ty,
+    variant: &Variant,
+    item_attrs: &attr::Item,
+    error: &Tokens,
+) -> Tokens {
+    let variant_ident = &variant.ident;
+
+    let block = match variant.style {
+        Style::Unit => {
+            quote! {
+                try!(_serde::Deserialize::deserialize(&mut deserializer));
+                Ok(#type_ident::#variant_ident)
+            }
+        }
+        Style::Newtype => {
+            let field_ty = &variant.fields[0].ty;
+            quote! {
+                <#field_ty as _serde::Deserialize<'de>>::deserialize(&mut deserializer)
+                    .map(#type_ident::#variant_ident)
+            }
+        }
+        Style::Tuple => {
+            deserialize_tuple(
+                type_ident,
+                Some(variant_ident),
+                impl_generics,
+                ty,
+                &variant.fields,
+                item_attrs,
+                None,
+                false,
+            )
+        }
+        Style::Struct => {
+            deserialize_struct(
+                type_ident,
+                Some(variant_ident),
+                impl_generics,
+                ty,
+                &variant.fields,
+                item_attrs,
+                None,
+                false,
+            )
+        }
+    };
+
+    quote! {
+        let mut deserializer = _serde::private::de::ContentDeserializer::<#error>::new(__content);
+        #block
+    }
+}
+
 fn deserialize_newtype_variant(
     type_ident: &syn::Ident,
     variant_ident: &syn::Ident,
@@ -642,18 +1378,38 @@ fn deserialize_newtype_variant(
     }
 }
 
+// `field_names` holds, for each field/variant, the primary deserialize
+// name followed by any `#[serde(alias = "...")]` names it also accepts.
+// Every name in a field's list becomes its own match arm pointing at the
+// same `__Field::__fieldN`, so a payload may use any of them.
 fn deserialize_field_visitor(
-    field_names: Vec<String>,
+    field_names: Vec<Vec<String>>,
+    item_attrs: &attr::Item,
+    is_variant: bool,
+) -> Tokens {
+    deserialize_field_visitor_impl(field_names, item_attrs, is_variant, false)
+}
+
+// When a struct has one or more `#[serde(flatten)]` fields, the `__Field`
+// visitor can no longer treat an unrecognized key as noise: the key and
+// its value both need to survive so the flattened field can later claim
+// them. `has_flatten` switches the catch-all from discarding the value to
+// capturing `(key, value)` into `__Field::__other`.
+fn deserialize_field_visitor_impl(
+    field_names: Vec<Vec<String>>,
     item_attrs: &attr::Item,
     is_variant: bool,
+    has_flatten: bool,
 ) -> Tokens {
     // Create the field names for the fields.
     let field_idents: &Vec<_> = &(0 .. field_names.len())
         .map(|i| aster::id(format!("__field{}", i)))
         .collect();
 
-    let ignore_variant = if is_variant || item_attrs.deny_unknown_fields() {
+    let ignore_variant = if is_variant || (item_attrs.deny_unknown_fields() && !has_flatten) {
         None
+    } else if has_flatten {
+        Some(quote!(__other(_serde::private::de::Content),))
     } else {
         Some(quote!(__ignore,))
     };
@@ -662,6 +1418,10 @@ fn deserialize_field_visitor(
         quote! {
             Err(_serde::de::Error::unknown_variant(value))
         }
+    } else if has_flatten {
+        quote! {
+            Ok(__Field::__other(_serde::private::de::Content::String(value.to_string())))
+        }
     } else if item_attrs.deny_unknown_fields() {
         quote! {
             Err(_serde::de::Error::unknown_field(value))
@@ -672,6 +1432,77 @@ fn deserialize_field_visitor(
         }
     };
 
+    let field_arms = field_names.iter()
+        .zip(field_idents.iter())
+        .map(|(names, ident)| {
+            quote! {
+                #(#names)|* => Ok(__Field::#ident),
+            }
+        });
+
+    // Indices are assigned positionally, ignoring aliases: a field is
+    // recognized by index `i` regardless of how many alternate string
+    // names it also accepts.
+    let index_arms = field_idents.iter()
+        .enumerate()
+        .map(|(i, ident)| {
+            let i = i as u64;
+            quote! {
+                #i => Ok(__Field::#ident),
+            }
+        });
+
+    let byte_arms = field_names.iter()
+        .zip(field_idents.iter())
+        .map(|(names, ident)| {
+            let mut byte_strs = Tokens::new();
+            for (i, name) in names.iter().enumerate() {
+                if i > 0 {
+                    byte_strs.append("|");
+                }
+                byte_strs.append(&format!("b{:?}", name));
+            }
+            quote! {
+                #byte_strs => Ok(__Field::#ident),
+            }
+        });
+
+    let fallthrough_arm_u64 = if is_variant {
+        quote! {
+            Err(_serde::de::Error::unknown_variant(""))
+        }
+    } else if has_flatten {
+        quote! {
+            Ok(__Field::__other(_serde::private::de::Content::U64(value)))
+        }
+    } else if item_attrs.deny_unknown_fields() {
+        quote! {
+            Err(_serde::de::Error::invalid_value("field index out of range"))
+        }
+    } else {
+        quote! {
+            Ok(__Field::__ignore)
+        }
+    };
+
+    let fallthrough_arm_bytes = if is_variant {
+        quote! {
+            Err(_serde::de::Error::unknown_variant(&_serde::private::de::str_lossy(value)))
+        }
+    } else if has_flatten {
+        quote! {
+            Ok(__Field::__other(_serde::private::de::Content::ByteBuf(value.to_vec())))
+        }
+    } else if item_attrs.deny_unknown_fields() {
+        quote! {
+            Err(_serde::de::Error::unknown_field(&_serde::private::de::str_lossy(value)))
+        }
+    } else {
+        quote! {
+            Ok(__Field::__ignore)
+        }
+    };
+
     quote! {
         #[allow(non_camel_case_types)]
         enum __Field {
@@ -679,26 +1510,42 @@ fn deserialize_field_visitor(
             #ignore_variant
         }
 
-        impl _serde::Deserialize for __Field {
+        impl<'de> _serde::Deserialize<'de> for __Field {
             #[inline]
             fn deserialize<__D>(deserializer: &mut __D) -> ::std::result::Result<__Field, __D::Error>
-                where __D: _serde::Deserializer,
+                where __D: _serde::Deserializer<'de>,
             {
                 struct __FieldVisitor;
 
-                impl _serde::de::Visitor for __FieldVisitor {
+                impl<'de> _serde::de::Visitor<'de> for __FieldVisitor {
                     type Value = __Field;
 
                     fn visit_str<__E>(&mut self, value: &str) -> ::std::result::Result<__Field, __E>
                         where __E: _serde::de::Error
                     {
                         match value {
-                            #(
-                                #field_names => Ok(__Field::#field_idents),
-                            )*
+                            #(#field_arms)*
                             _ => #fallthrough_arm
                         }
                     }
+
+                    fn visit_u64<__E>(&mut self, value: u64) -> ::std::result::Result<__Field, __E>
+                        where __E: _serde::de::Error
+                    {
+                        match value {
+                            #(#index_arms)*
+                            _ => #fallthrough_arm_u64
+                        }
+                    }
+
+                    fn visit_bytes<__E>(&mut self, value: &[u8]) -> ::std::result::Result<__Field, __E>
+                        where __E: _serde::de::Error
+                    {
+                        match value {
+                            #(#byte_arms)*
+                            _ => #fallthrough_arm_bytes
+                        }
+                    }
                 }
 
                 deserializer.deserialize_struct_field(__FieldVisitor)
@@ -707,6 +1554,15 @@ fn deserialize_field_visitor(
     }
 }
 
+// Builds the primary-name-plus-aliases list for a single field, used both
+// by the `__Field` visitor above and by anything that needs to know every
+// name a field may be addressed by.
+fn field_names_with_aliases(name: String, aliases: &[String]) -> Vec<String> {
+    let mut names = vec![name];
+    names.extend(aliases.iter().cloned());
+    names
+}
+
 fn deserialize_struct_visitor(
     type_ident: &syn::Ident,
     struct_path: Tokens,
@@ -714,14 +1570,23 @@ fn deserialize_struct_visitor(
     fields: &[Field],
     item_attrs: &attr::Item,
 ) -> (Tokens, Tokens, Tokens) {
+    let has_flatten = fields.iter().any(|field| field.attrs.flatten());
+
+    // Flattened fields have no key of their own in the input, so they do
+    // not get a `__Field::__fieldN` variant and are excluded from `FIELDS`
+    // as well; they absorb whatever the `__other` catch-all collects.
     let field_exprs = fields.iter()
-        .map(|field| field.attrs.name().deserialize_name())
+        .filter(|field| !field.attrs.flatten())
+        .map(|field| field_names_with_aliases(
+            field.attrs.name().deserialize_name(),
+            field.attrs.aliases()))
         .collect();
 
-    let field_visitor = deserialize_field_visitor(
+    let field_visitor = deserialize_field_visitor_impl(
         field_exprs,
         item_attrs,
         false,
+        has_flatten,
     );
 
     let visit_map = deserialize_map(
@@ -732,9 +1597,11 @@ fn deserialize_struct_visitor(
         item_attrs,
     );
 
-    let field_names = fields.iter().map(|field| {
-        field.ident.clone().expect("struct contains unnamed field").to_string()
-    });
+    let field_names = fields.iter()
+        .filter(|field| !field.attrs.flatten())
+        .map(|field| {
+            field.ident.clone().expect("struct contains unnamed field").to_string()
+        });
 
     let fields_stmt = quote! {
         const FIELDS: &'static [&'static str] = &[ #(#field_names),* ];
@@ -750,6 +1617,8 @@ fn deserialize_map(
     fields: &[Field],
     item_attrs: &attr::Item,
 ) -> Tokens {
+    let has_flatten = fields.iter().any(|field| field.attrs.flatten());
+
     if fields.is_empty() && item_attrs.deny_unknown_fields() {
         return quote! {
             // FIXME: Once we drop support for Rust 1.15:
@@ -760,8 +1629,15 @@ fn deserialize_map(
         };
     }
 
-    // Create the field names for the fields.
+    let flatten_fields: Vec<_> = fields.iter()
+        .filter(|field| field.attrs.flatten())
+        .collect();
+
+    // Create the field names for the non-flattened fields, in the same
+    // filtered order `deserialize_struct_visitor` used to build `__Field`,
+    // so `__fieldN` lines up with the same field in both places.
     let fields_names = fields.iter()
+        .filter(|field| !field.attrs.flatten())
         .enumerate()
         .map(|(i, field)|
              (field, aster::id(format!("__field{}", i))))
@@ -825,8 +1701,17 @@ fn deserialize_map(
         })
         .collect::<Vec<_>>();
 
-    // Visit ignored values to consume them
-    let ignored_arm = if item_attrs.deny_unknown_fields() {
+    // When one or more fields are flattened, an unrecognized key is not an
+    // error (or noise to discard) — it, and its value, are buffered so the
+    // flattened field(s) can claim them once all keys have been seen.
+    let ignored_arm = if has_flatten {
+        Some(quote! {
+            __Field::__other(__name) => {
+                let __value = try!(visitor.visit_value::<_serde::private::de::Content>());
+                __collect.push((__name, __value));
+            }
+        })
+    } else if item_attrs.deny_unknown_fields() {
         None
     } else {
         Some(quote! {
@@ -834,6 +1719,14 @@ fn deserialize_map(
         })
     };
 
+    let collect_let = if has_flatten {
+        Some(quote! {
+            let mut __collect = Vec::<(_serde::private::de::Content, _serde::private::de::Content)>::new();
+        })
+    } else {
+        None
+    };
+
     let extract_values = fields_names.iter()
         .filter(|&&(field, _)| !field.attrs.skip_deserializing())
         .map(|&(field, ref name)| {
@@ -848,7 +1741,23 @@ fn deserialize_map(
         })
         .collect::<Vec<_>>();
 
-    let result = fields_names.iter()
+    // Each flattened field is deserialized from whatever was left over in
+    // `__collect` after the loop above, via a `FlatMapDeserializer` that
+    // treats the buffered `(Content, Content)` pairs as a map. Multiple
+    // flattened fields share the same buffer, consuming what they
+    // recognize and leaving the rest for the next one.
+    let flatten_extract: Vec<_> = flatten_fields.iter()
+        .map(|field| {
+            let ident = field.ident.clone().expect("struct contains unnamed fields");
+            let field_ty = &field.ty;
+            quote! {
+                let #ident: #field_ty = try!(_serde::Deserialize::deserialize(
+                    _serde::private::de::FlatMapDeserializer(&mut __collect)));
+            }
+        })
+        .collect();
+
+    let mut result: Vec<_> = fields_names.iter()
         .map(|&(field, ref name)| {
             let ident = field.ident.clone().expect("struct contains unnamed fields");
             let value = if field.attrs.skip_deserializing() {
@@ -857,10 +1766,17 @@ fn deserialize_map(
                 quote!(#name)
             };
             quote!(#ident: #value)
-        });
+        })
+        .collect();
+
+    result.extend(flatten_fields.iter().map(|field| {
+        let ident = field.ident.clone().expect("struct contains unnamed fields");
+        quote!(#ident: #ident)
+    }));
 
     quote! {
         #(#let_values)*
+        #collect_let
 
         while let Some(key) = try!(visitor.visit_key::<__Field>()) {
             match key {
@@ -873,6 +1789,7 @@ fn deserialize_map(
         try!(visitor.end());
 
         #(#extract_values)*
+        #(#flatten_extract)*
 
         Ok(#struct_path { #(#result),* })
     }
@@ -895,6 +1812,9 @@ fn wrap_deserialize_with(
         .build();
 
     let where_clause = &impl_generics.where_clause;
+    // `deserialize_with` fields are exempt from the `&str` borrow check
+    // (see `check_no_str`), so this wrapper never needs a `'de: 'a` bound.
+    let de_impl_generics = de_generics(impl_generics, &[]);
 
     let phantom_ty = aster::path()
         .segment(type_ident)
@@ -912,9 +1832,9 @@ fn wrap_deserialize_with(
             }
         },
         quote! {
-            impl #impl_generics _serde::Deserialize for #wrapper_ty #where_clause {
+            impl #de_impl_generics _serde::Deserialize<'de> for #wrapper_ty #where_clause {
                 fn deserialize<__D>(__d: &mut __D) -> ::std::result::Result<Self, __D::Error>
-                    where __D: _serde::Deserializer
+                    where __D: _serde::Deserializer<'de>
                 {
                     let value = try!(#deserialize_with(__d));
                     Ok(__SerdeDeserializeWithStruct {
@@ -963,7 +1883,11 @@ fn check_no_str(cx: &internals::Ctxt, item: &Item) {
 
     for field in item.body.all_fields() {
         if field.attrs.skip_deserializing()
-            || field.attrs.deserialize_with().is_some() { continue }
+            || field.attrs.deserialize_with().is_some()
+            // Fields marked `#[serde(borrow)]`, or inferred as borrowable,
+            // deserialize through the `'de` lifetime we now thread through
+            // every generated impl, so `&str` is fine for them.
+            || !field.attrs.borrowed_lifetimes().is_empty() { continue }
 
         if let syn::Ty::Rptr(_, ref inner) = *field.ty {
             if let syn::Ty::Path(_, ref path) = inner.ty {
@@ -975,3 +1899,66 @@ fn check_no_str(cx: &internals::Ctxt, item: &Item) {
         }
     }
 }
+
+// `#[serde(flatten)]` works by buffering every key the known fields don't
+// claim and replaying it into the flattened field, which is exactly what
+// "unknown field" would otherwise mean. Catching this at derive time saves
+// the user from `deny_unknown_fields` silently doing nothing once a field
+// is flattened.
+fn check_flatten_with_deny_unknown_fields(cx: &internals::Ctxt, item: &Item) {
+    if !item_has_flatten(item) {
+        return;
+    }
+
+    if item.attrs.deny_unknown_fields() {
+        cx.error(
+            "#[serde(deny_unknown_fields)] cannot be used with #[serde(flatten)]");
+    }
+}
+
+fn item_has_flatten(item: &Item) -> bool {
+    item.body.all_fields().any(|field| field.attrs.flatten())
+}
+
+// `#[serde(remote = "...")]` is only wired up for the two body shapes that
+// build a single value inline (a tuple/newtype struct or a named-field
+// struct) — `deserialize_item_enum` and `deserialize_unit_struct` never
+// received the remote type, yet `expand_derive_deserialize` unconditionally
+// returns `#remote` from the generated function regardless of body shape.
+// Reject the combination at derive time instead of emitting a function
+// whose body builds the local type but whose signature promises `#remote`.
+fn check_remote_on_supported_body(cx: &internals::Ctxt, item: &Item) {
+    if item.attrs.remote().is_none() {
+        return;
+    }
+
+    match item.body {
+        Body::Struct(Style::Tuple, _) |
+        Body::Struct(Style::Newtype, _) |
+        Body::Struct(Style::Struct, _) => {}
+        Body::Enum(_) | Body::Struct(Style::Unit, _) => {
+            cx.error(
+                "#[serde(remote = \"...\")] is not supported on this type; \
+                 only tuple structs and structs with named fields can be used as a remote mirror");
+        }
+    }
+}
+
+// `tag`/`content`/`untagged` only make sense when picking which enum
+// variant to deserialize into; on a struct they would silently do nothing,
+// so catch the misuse at derive time instead.
+fn check_tag_only_on_enum(cx: &internals::Ctxt, item: &Item) {
+    match item.body {
+        Body::Enum(_) => {}
+        Body::Struct(..) => {
+            match *item.attrs.tag() {
+                attr::EnumTag::External => {}
+                _ => {
+                    cx.error(
+                        "#[serde(tag = \"...\")], #[serde(tag = \"...\", content = \"...\")], \
+                         and #[serde(untagged)] may only be used on enums");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,126 @@
+// Case conversion rules for `#[serde(rename_all = "...")]`. Converts a
+// Rust-style field or variant identifier (`snake_case` for fields,
+// `PascalCase` for variants) into the wire name implied by the chosen
+// rule, so a whole struct/enum can opt into a naming convention without
+// a `#[serde(rename = "...")]` on every member.
+//
+// An explicit `#[serde(rename = "...")]` on a field/variant always wins
+// over whatever `rename_all` would have produced; that precedence is
+// enforced where `Name`s are resolved, which is `attr::Item::from_ast`/
+// `attr::Field::from_ast` in `internals::attr`. That module is not part
+// of this checkout (only `serde_codegen/src/de.rs` and this file are present here), so
+// `RenameRule` cannot yet be reached from a `#[serde(rename_all = "...")]`
+// attribute: `de.rs` only ever consumes the already-resolved `Name` that
+// `internals::attr` hands it, never a raw rule. Tracked as a follow-up
+// against `internals::attr` rather than left half-wired here.
+
+#[derive(Copy, Clone, PartialEq)]
+pub enum RenameRule {
+    /// Don't apply a rename rule.
+    None,
+    /// Rename direct children to "lowercase" style.
+    LowerCase,
+    /// Rename direct children to "UPPERCASE" style.
+    UpperCase,
+    /// Rename direct children to "PascalCase" style, as typically used for
+    /// enum variants.
+    PascalCase,
+    /// Rename direct children to "camelCase" style.
+    CamelCase,
+    /// Rename direct children to "snake_case" style, as typically used for
+    /// fields.
+    SnakeCase,
+    /// Rename direct children to "SCREAMING_SNAKE_CASE" style.
+    ScreamingSnakeCase,
+    /// Rename direct children to "kebab-case" style.
+    KebabCase,
+    /// Rename direct children to "SCREAMING-KEBAB-CASE" style.
+    ScreamingKebabCase,
+}
+
+impl RenameRule {
+    pub fn from_str(rename_all_str: &str) -> Result<Self, String> {
+        match rename_all_str {
+            "lowercase" => Ok(RenameRule::LowerCase),
+            "UPPERCASE" => Ok(RenameRule::UpperCase),
+            "PascalCase" => Ok(RenameRule::PascalCase),
+            "camelCase" => Ok(RenameRule::CamelCase),
+            "snake_case" => Ok(RenameRule::SnakeCase),
+            "SCREAMING_SNAKE_CASE" => Ok(RenameRule::ScreamingSnakeCase),
+            "kebab-case" => Ok(RenameRule::KebabCase),
+            "SCREAMING-KEBAB-CASE" => Ok(RenameRule::ScreamingKebabCase),
+            _ => Err(format!("unknown rename rule for #[serde(rename_all = \"{}\")]", rename_all_str)),
+        }
+    }
+
+    /// Apply a renaming rule to a field, returning the version expected in the source.
+    pub fn apply_to_field(&self, field: &str) -> String {
+        match *self {
+            RenameRule::None | RenameRule::SnakeCase => field.to_owned(),
+            RenameRule::LowerCase => field.replace('_', ""),
+            RenameRule::UpperCase => field.replace('_', "").to_uppercase(),
+            RenameRule::PascalCase => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for ch in field.chars() {
+                    if ch == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.extend(ch.to_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(ch);
+                    }
+                }
+                pascal
+            }
+            RenameRule::CamelCase => {
+                let mut pascal = RenameRule::PascalCase.apply_to_field(field);
+                if let Some(first) = pascal.clone().chars().next() {
+                    pascal.replace_range(..1, &first.to_lowercase().to_string());
+                }
+                pascal
+            }
+            RenameRule::ScreamingSnakeCase => field.to_uppercase(),
+            RenameRule::KebabCase => field.replace('_', "-"),
+            RenameRule::ScreamingKebabCase => {
+                RenameRule::KebabCase.apply_to_field(field).to_uppercase()
+            }
+        }
+    }
+
+    /// Apply a renaming rule to a variant, returning the version expected in the source.
+    pub fn apply_to_variant(&self, variant: &str) -> String {
+        match *self {
+            RenameRule::None | RenameRule::PascalCase => variant.to_owned(),
+            RenameRule::LowerCase => variant.to_lowercase(),
+            RenameRule::UpperCase => variant.to_uppercase(),
+            RenameRule::CamelCase => {
+                let mut camel = variant.to_owned();
+                if let Some(first) = camel.clone().chars().next() {
+                    camel.replace_range(..1, &first.to_lowercase().to_string());
+                }
+                camel
+            }
+            RenameRule::SnakeCase => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.extend(ch.to_lowercase());
+                }
+                snake
+            }
+            RenameRule::ScreamingSnakeCase => {
+                RenameRule::SnakeCase.apply_to_variant(variant).to_uppercase()
+            }
+            RenameRule::KebabCase => {
+                RenameRule::SnakeCase.apply_to_variant(variant).replace('_', "-")
+            }
+            RenameRule::ScreamingKebabCase => {
+                RenameRule::KebabCase.apply_to_variant(variant).to_uppercase()
+            }
+        }
+    }
+}